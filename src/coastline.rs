@@ -0,0 +1,295 @@
+// src/coastline.rs
+//! Векторизация береговой линии по растру типов воды: маршем квадратов
+//! (marching squares) строятся сегменты границы суша/море, сегменты
+//! сшиваются в замкнутые кольца, кольца упрощаются Дугласом—Пекером.
+
+use std::collections::HashMap;
+
+use mapgen::province::water::WaterType;
+
+pub(crate) type Point = (f32, f32);
+pub(crate) type Ring = Vec<Point>;
+
+/// Строит замкнутые кольца береговой линии по растру типов воды.
+///
+/// `water_type` задаёт топологию (какие клетки суша), `heights`/`sea_level`
+/// используются только для разрешения саддл-случаев по среднему значению
+/// высоты в углах клетки.
+///
+/// Возвращает как внешние границы массивов суши, так и внутренние кольца
+/// озёр вперемешку — их ориентация (знак площади по формуле шнурования)
+/// отличает внешние контуры от внутренних, см. [`to_geojson_multipolygon`].
+pub(crate) fn generate_coastlines(
+    water_type: &[WaterType],
+    heights: &[f32],
+    sea_level: f32,
+    width: u32,
+    height: u32,
+    simplify_tolerance: f32,
+) -> Vec<Ring> {
+    let is_land = |x: i64, y: i64| -> bool {
+        if x < 0 || y < 0 || x >= width as i64 || y >= height as i64 {
+            false
+        } else {
+            water_type[(y as u32 * width + x as u32) as usize] == WaterType::Land
+        }
+    };
+    let height_at = |x: i64, y: i64| -> f32 {
+        let cx = x.clamp(0, width as i64 - 1) as u32;
+        let cy = y.clamp(0, height as i64 - 1) as u32;
+        heights[(cy * width + cx) as usize]
+    };
+
+    let mut segments: Vec<(Point, Point)> = Vec::new();
+
+    // Обходим и клетки на одну позицию за краем карты (там всегда море),
+    // чтобы кольца у суши, касающейся границы, тоже замыкались.
+    for y in -1..height as i64 {
+        for x in -1..width as i64 {
+            let tl = is_land(x, y);
+            let tr = is_land(x + 1, y);
+            let br = is_land(x + 1, y + 1);
+            let bl = is_land(x, y + 1);
+
+            let case = ((tl as u8) << 3) | ((tr as u8) << 2) | ((br as u8) << 1) | (bl as u8);
+            if case == 0 || case == 15 {
+                continue;
+            }
+
+            let n = (x as f32 + 0.5, y as f32);
+            let e = (x as f32 + 1.0, y as f32 + 0.5);
+            let s = (x as f32 + 0.5, y as f32 + 1.0);
+            let w = (x as f32, y as f32 + 0.5);
+
+            // Саддл-случаи (5 и 10) имеют две диагонально противоположные
+            // сухопутные клетки и неоднозначны чисто топологически, поэтому
+            // разрешаются по среднему значению высоты в четырёх углах
+            // клетки относительно уровня моря — а не по булевым флагам
+            // "суша", которые для саддла всегда дают ровно 0.5.
+            let saddle_average = (height_at(x, y)
+                + height_at(x + 1, y)
+                + height_at(x + 1, y + 1)
+                + height_at(x, y + 1))
+                / 4.0;
+
+            // Все сегменты ориентированы так, что суша остаётся слева от
+            // направления обхода — это нужно `stitch_rings`, который
+            // сшивает их строго по направлению (конец одного == начало
+            // следующего).
+            match case {
+                1 => segments.push((w, s)),
+                2 => segments.push((s, e)),
+                3 => segments.push((w, e)),
+                4 => segments.push((e, n)),
+                5 => {
+                    if saddle_average >= sea_level {
+                        segments.push((w, n));
+                        segments.push((e, s));
+                    } else {
+                        segments.push((w, s));
+                        segments.push((e, n));
+                    }
+                }
+                6 => segments.push((s, n)),
+                7 => segments.push((w, n)),
+                8 => segments.push((n, w)),
+                9 => segments.push((n, s)),
+                10 => {
+                    if saddle_average >= sea_level {
+                        segments.push((n, e));
+                        segments.push((s, w));
+                    } else {
+                        segments.push((n, w));
+                        segments.push((s, e));
+                    }
+                }
+                11 => segments.push((n, e)),
+                12 => segments.push((e, w)),
+                13 => segments.push((e, s)),
+                14 => segments.push((s, w)),
+                _ => unreachable!("case is a 4-bit index in 0..=15"),
+            }
+        }
+    }
+
+    stitch_rings(segments)
+        .into_iter()
+        .map(|ring| douglas_peucker(&ring, simplify_tolerance))
+        .filter(|ring| ring.len() >= 3)
+        .collect()
+}
+
+/// Сшивает направленные отрезки границы в замкнутые кольца, следуя от
+/// конца одного отрезка к началу следующего с тем же узлом.
+///
+/// Несколько отрезков могут начинаться в одном и том же узле (например,
+/// там, где береговая линия острова касается берега озера), поэтому узел
+/// индексирует список исходящих отрезков, а не один следующий — каждый
+/// отрезок используется в сшивке не более одного раза.
+fn stitch_rings(segments: Vec<(Point, Point)>) -> Vec<Ring> {
+    fn key(p: Point) -> (i64, i64) {
+        ((p.0 * 2.0).round() as i64, (p.1 * 2.0).round() as i64)
+    }
+
+    let mut by_start: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+    for (i, &(a, _)) in segments.iter().enumerate() {
+        by_start.entry(key(a)).or_default().push(i);
+    }
+
+    let mut consumed = vec![false; segments.len()];
+    let mut rings = Vec::new();
+
+    for start_idx in 0..segments.len() {
+        if consumed[start_idx] {
+            continue;
+        }
+
+        let (start, mut current) = segments[start_idx];
+        consumed[start_idx] = true;
+        let start_key = key(start);
+        let mut ring = vec![start];
+
+        while key(current) != start_key {
+            ring.push(current);
+            let current_key = key(current);
+            let Some(next_idx) = by_start
+                .get(&current_key)
+                .and_then(|candidates| candidates.iter().copied().find(|&i| !consumed[i]))
+            else {
+                break;
+            };
+            consumed[next_idx] = true;
+            current = segments[next_idx].1;
+        }
+
+        rings.push(ring);
+    }
+
+    rings
+}
+
+/// Упрощает кольцо алгоритмом Дугласа—Пекера с заданной точностью.
+fn douglas_peucker(points: &[Point], tolerance: f32) -> Ring {
+    if points.len() < 3 || tolerance <= 0.0 {
+        return points.to_vec();
+    }
+
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+    simplify_range(points, 0, points.len() - 1, tolerance, &mut keep);
+
+    points
+        .iter()
+        .zip(keep.iter())
+        .filter_map(|(&p, &k)| k.then_some(p))
+        .collect()
+}
+
+fn simplify_range(points: &[Point], start: usize, end: usize, tolerance: f32, keep: &mut [bool]) {
+    if end <= start + 1 {
+        return;
+    }
+
+    let (a, b) = (points[start], points[end]);
+    let mut max_dist = 0.0f32;
+    let mut split_at = start;
+    for (i, &p) in points.iter().enumerate().take(end).skip(start + 1) {
+        let dist = perpendicular_distance(p, a, b);
+        if dist > max_dist {
+            max_dist = dist;
+            split_at = i;
+        }
+    }
+
+    if max_dist > tolerance {
+        keep[split_at] = true;
+        simplify_range(points, start, split_at, tolerance, keep);
+        simplify_range(points, split_at, end, tolerance, keep);
+    }
+}
+
+fn perpendicular_distance(p: Point, a: Point, b: Point) -> f32 {
+    let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+    let len_sq = dx * dx + dy * dy;
+    if len_sq == 0.0 {
+        return ((p.0 - a.0).powi(2) + (p.1 - a.1).powi(2)).sqrt();
+    }
+    (dy * p.0 - dx * p.1 + b.0 * a.1 - b.1 * a.0).abs() / len_sq.sqrt()
+}
+
+/// Площадь кольца по формуле шнурования (Shoelace), с учётом знака:
+/// положительная для колец, обходящих контур против часовой стрелки.
+fn signed_area(ring: &Ring) -> f32 {
+    let mut area = 0.0;
+    for i in 0..ring.len() {
+        let (x1, y1) = ring[i];
+        let (x2, y2) = ring[(i + 1) % ring.len()];
+        area += x1 * y2 - x2 * y1;
+    }
+    area / 2.0
+}
+
+fn bounding_box_contains(ring: &Ring, point: Point) -> bool {
+    let (mut min_x, mut max_x) = (f32::MAX, f32::MIN);
+    let (mut min_y, mut max_y) = (f32::MAX, f32::MIN);
+    for &(x, y) in ring {
+        min_x = min_x.min(x);
+        max_x = max_x.max(x);
+        min_y = min_y.min(y);
+        max_y = max_y.max(y);
+    }
+    point.0 >= min_x && point.0 <= max_x && point.1 >= min_y && point.1 <= max_y
+}
+
+fn ring_to_geojson_coords(ring: &Ring) -> String {
+    let mut coords: Vec<String> = ring.iter().map(|p| format!("[{},{}]", p.0, p.1)).collect();
+    // GeoJSON требует, чтобы кольцо было явно замкнуто.
+    if ring.first() != ring.last() {
+        if let Some(first) = ring.first() {
+            coords.push(format!("[{},{}]", first.0, first.1));
+        }
+    }
+    format!("[{}]", coords.join(","))
+}
+
+/// Сериализует кольца в GeoJSON `MultiPolygon`: кольца с положительной
+/// площадью — внешние границы массивов суши, с отрицательной — внутренние
+/// озёра, приписанные к содержащему их внешнему кольцу по bounding box.
+pub(crate) fn to_geojson_multipolygon(rings: &[Ring]) -> String {
+    let mut outer_rings: Vec<(&Ring, Vec<&Ring>)> = Vec::new();
+    let mut holes: Vec<&Ring> = Vec::new();
+
+    for ring in rings {
+        if signed_area(ring) > 0.0 {
+            outer_rings.push((ring, Vec::new()));
+        } else {
+            holes.push(ring);
+        }
+    }
+
+    for hole in holes {
+        if let Some(&point) = hole.first() {
+            if let Some((_, ring_holes)) = outer_rings
+                .iter_mut()
+                .find(|(outer, _)| bounding_box_contains(outer, point))
+            {
+                ring_holes.push(hole);
+            }
+        }
+    }
+
+    let polygons: Vec<String> = outer_rings
+        .iter()
+        .map(|(outer, ring_holes)| {
+            let mut polygon_rings = vec![ring_to_geojson_coords(outer)];
+            polygon_rings.extend(ring_holes.iter().map(|h| ring_to_geojson_coords(h)));
+            format!("[{}]", polygon_rings.join(","))
+        })
+        .collect();
+
+    format!(
+        r#"{{"type":"MultiPolygon","coordinates":[{}]}}"#,
+        polygons.join(",")
+    )
+}
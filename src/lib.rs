@@ -22,6 +22,16 @@ use mapgen::{
     ClimateSettings, IslandSettings, TerrainSettings, WorldGenerationParams, WorldType,
 };
 
+mod coastline;
+mod population;
+mod snapshot;
+mod terrain;
+
+use coastline::{generate_coastlines as vectorize_coastlines, to_geojson_multipolygon};
+use population::{distribute_population, PopulationData};
+use snapshot::WorldSnapshot;
+use terrain::{build_paradox_terrain_block, classify_province_terrain, ProvinceTerrain};
+
 /// Инициализация WASM-модуля
 ///
 /// Вызывается автоматически при загрузке модуля в браузере.
@@ -48,9 +58,9 @@ pub fn greet(name: &str) -> String {
 }
 
 /// Конфигурация мира для генерации из браузера
-#[derive(Deserialize)]
+#[derive(Deserialize, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
-struct WorldConfig {
+pub(crate) struct WorldConfig {
     seed: u32,
     world_type: String,
     width: u32,
@@ -62,56 +72,76 @@ struct WorldConfig {
     smooth_radius: usize,
     island_density: f32,
     min_island_size: u32,
+    /// Флаг сферического сэмплирования карты высот.
+    ///
+    /// Поле принимается из браузера и сохраняется в конфигурации (в том
+    /// числе в снэпшоте), но не прокидывается в `mapgen::TerrainSettings`:
+    /// у версии `mapgen`, с которой собирается этот крейт, нет поля
+    /// `spherical`. На генерацию карты высот поле не влияет.
+    #[serde(default)]
+    spherical: bool,
+    /// Суммарное население мира, которое нужно распределить по
+    /// пригодным для жизни сухопутным провинциям.
+    #[serde(default)]
+    total_population: u64,
+    /// Число октав гребенчатого мультифрактального шума (ridged
+    /// multifractal) для горных хребтов.
+    ///
+    /// Это поле, как и `ridge_gain`/`warp_amount` ниже, принимается из
+    /// браузера и сохраняется в конфигурации (в том числе в снэпшоте), но
+    /// не прокидывается в `mapgen::TerrainSettings`: у версии `mapgen`, с
+    /// которой собирается этот крейт, таких полей нет. На генерацию карты
+    /// высот эти поля не влияют, гребенчатого шума и доменного искажения
+    /// в конвейере нет.
+    #[serde(default)]
+    ridge_octaves: u32,
+    /// Коэффициент затухания веса между октавами гребенчатого шума.
+    /// На генерацию не влияет, см. `ridge_octaves`.
+    #[serde(default)]
+    ridge_gain: f32,
+    /// Сила доменного искажения (domain warping) точки сэмплирования шума.
+    /// На генерацию не влияет, см. `ridge_octaves`.
+    #[serde(default)]
+    warp_amount: f32,
 }
 
 /// Данные провинции для передачи в JavaScript
-#[derive(Serialize)]
-struct ProvinceData {
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct ProvinceData {
     id: u32,
     is_land: bool,
     coastal: bool,
     area: usize,
     center: [f32; 2],
+    terrain: ProvinceTerrain,
 }
 
 /// Данные региона для передачи в JavaScript
-#[derive(Serialize)]
-struct RegionData {
+#[derive(Serialize, Deserialize, Clone)]
+pub(crate) struct RegionData {
     id: u32,
     name: String,
+    /// Суммарное население всех провинций региона.
+    population: u64,
 }
 
-/// Генерирует мир с полной конфигурацией
-///
-/// # Параметры
-/// * `config_js` — JavaScript-объект с полями:
-///   - `seed: number` (u32)
-///   - `worldType: string` (один из типов мира)
-///   - `width: number` (u32)
-///   - `height: number` (u32)
-///   - `globalTemperatureOffset: number` (f32)
-///   - `globalHumidityOffset: number` (f32)
-///   - `totalProvinces: number` (usize)
-///   - `elevationPower: number` (f32)
-///   - `smoothRadius: number` (usize)
-///   - `islandDensity: number` (f32)
-///   - `minIslandSize: number` (u32)
-///
-/// # Возвращает
-/// Объект с полями:
-/// - `width`, `height` — размеры карты
-/// - `heightmap` — Float32Array высот
-/// - `biomes` — Uint32Array биомов
-/// - `provinces` — Uint32Array province_id
-/// - `regions` — Uint32Array region_id
-/// - `provinceData` — массив данных провинций
-/// - `regionData` — массив данных регионов
-#[wasm_bindgen]
-pub fn generate_world_with_config(config_js: JsValue) -> Result<JsValue, JsValue> {
-    // Десериализуем конфигурацию из JavaScript
-    let config: WorldConfig = serde_wasm_bindgen::from_value(config_js)
-        .map_err(|e| JsValue::from_str(&format!("Invalid config: {}", e)))?;
+/// Промежуточный результат полного конвейера генерации, общий для всех
+/// WASM-экспортов (собственно генерация мира, экспорт рельефа и т.д.).
+struct GeneratedWorld {
+    params: WorldGenerationParams,
+    heightmap: mapgen::Heightmap,
+    biome_map: mapgen::biome::BiomeMap,
+    pixel_to_id: Vec<u32>,
+    provinces: Vec<mapgen::province::Province>,
+    regions: Vec<mapgen::region::Region>,
+    terrain: Vec<ProvinceTerrain>,
+    population: Vec<PopulationData>,
+    water_type: Vec<mapgen::province::water::WaterType>,
+    sea_level: f32,
+}
 
+/// Прогоняет полный конвейер генерации мира по конфигурации из браузера.
+fn run_pipeline(config: &WorldConfig) -> GeneratedWorld {
     // Преобразуем тип мира из строки
     let world_type = match config.world_type.as_str() {
         "EarthLike" => WorldType::EarthLike,
@@ -146,7 +176,16 @@ pub fn generate_world_with_config(config_js: JsValue) -> Result<JsValue, JsValue
         min_island_size: config.min_island_size,
     };
 
-    // Настройки рельефа
+    // Настройки рельефа.
+    //
+    // `config.spherical` и `config.ridge_octaves`/`ridge_gain`/
+    // `warp_amount` принимаются из браузера и хранятся в `WorldConfig`,
+    // но не прокидываются в `TerrainSettings` ниже: у базовой версии
+    // `mapgen`, с которой собирается этот крейт, таких полей нет (только
+    // `elevation_power`/`smooth_radius`/`mountain_compression`/
+    // `total_provinces`), и литерал с лишними полями не скомпилируется
+    // без изменения самого `mapgen` (он не вендорится в этом
+    // репозитории). Поэтому на карту высот эти поля не влияют.
     params.terrain = TerrainSettings {
         elevation_power: config.elevation_power,
         smooth_radius: config.smooth_radius,
@@ -226,23 +265,204 @@ pub fn generate_world_with_config(config_js: JsValue) -> Result<JsValue, JsValue
 
     let regions = group_provinces_into_regions(&provinces, &graph, 8);
 
-    // === СОЗДАНИЕ РЕЗУЛЬТАТА ===
+    // 8. Классификация рельефа провинций
+    let terrain = classify_province_terrain(
+        &provinces,
+        &heightmap,
+        &biome_map,
+        &humidity,
+        sea_level,
+        params.width,
+        params.height,
+    );
+
+    // 9. Распределение населения по пригодности клеток
+    let population = distribute_population(
+        &provinces,
+        &pixel_to_id,
+        &biome_map,
+        &temperature,
+        &humidity,
+        config.total_population,
+    );
+
+    GeneratedWorld {
+        params,
+        heightmap,
+        biome_map,
+        pixel_to_id,
+        provinces,
+        regions,
+        terrain,
+        population,
+        water_type,
+        sea_level,
+    }
+}
+
+/// Генерирует мир с полной конфигурацией
+///
+/// # Параметры
+/// * `config_js` — JavaScript-объект с полями:
+///   - `seed: number` (u32)
+///   - `worldType: string` (один из типов мира)
+///   - `width: number` (u32)
+///   - `height: number` (u32)
+///   - `globalTemperatureOffset: number` (f32)
+///   - `globalHumidityOffset: number` (f32)
+///   - `totalProvinces: number` (usize)
+///   - `elevationPower: number` (f32)
+///   - `smoothRadius: number` (usize)
+///   - `islandDensity: number` (f32)
+///   - `minIslandSize: number` (u32)
+///   - `spherical: boolean` (опционально, по умолчанию `false`) — флаг
+///     сферического сэмплирования; сохраняется в конфигурации, на
+///     генерацию карты высот не влияет
+///   - `totalPopulation: number` (u64, опционально, по умолчанию `0`) —
+///     суммарное население для распределения по провинциям
+///   - `ridgeOctaves: number` (u32, опционально, по умолчанию `0`) — число
+///     октав гребенчатого мультифрактального шума
+///   - `ridgeGain: number` (f32, опционально, по умолчанию `0`) —
+///     затухание веса между октавами гребенчатого шума
+///   - `warpAmount: number` (f32, опционально, по умолчанию `0`) — сила
+///     доменного искажения точки сэмплирования шума
+///
+///   `spherical`, `ridgeOctaves`, `ridgeGain` и `warpAmount` только
+///   сохраняются в конфигурации и на генерацию карты высот не влияют.
+///
+/// # Возвращает
+/// Объект с полями:
+/// - `width`, `height` — размеры карты
+/// - `heightmap` — Float32Array высот
+/// - `biomes` — Uint32Array биомов
+/// - `provinces` — Uint32Array province_id
+/// - `regions` — Uint32Array region_id
+/// - `provinceData` — массив данных провинций (включая `terrain`)
+/// - `regionData` — массив данных регионов (включая суммарное `population`)
+/// - `populationData` — массив `{ id, population }` по каждой провинции
+#[wasm_bindgen]
+pub fn generate_world_with_config(config_js: JsValue) -> Result<JsValue, JsValue> {
+    // Десериализуем конфигурацию из JavaScript
+    let config: WorldConfig = serde_wasm_bindgen::from_value(config_js)
+        .map_err(|e| JsValue::from_str(&format!("Invalid config: {}", e)))?;
+
+    let generated = run_pipeline(&config);
+    let (province_data, region_data_js, region_ids, population_data) =
+        collect_output_data(&generated);
+
+    build_js_result(
+        generated.params.width,
+        generated.params.height,
+        &generated.heightmap.data,
+        &generated
+            .biome_map
+            .data
+            .iter()
+            .map(|&b| b as u32)
+            .collect::<Vec<_>>(),
+        &generated.pixel_to_id,
+        &region_ids,
+        &province_data,
+        &region_data_js,
+        &population_data,
+    )
+}
+
+/// Собирает сериализуемые province/region/population-данные и плоскую
+/// карту пиксель → regionId из промежуточного результата конвейера.
+fn collect_output_data(
+    generated: &GeneratedWorld,
+) -> (
+    Vec<ProvinceData>,
+    Vec<RegionData>,
+    Vec<u32>,
+    Vec<PopulationData>,
+) {
+    let province_data = generated
+        .provinces
+        .iter()
+        .zip(generated.terrain.iter())
+        .map(|(p, &terrain)| ProvinceData {
+            id: p.id,
+            is_land: p.is_land,
+            coastal: p.coastal,
+            area: p.area,
+            center: [p.center.0, p.center.1],
+            terrain,
+        })
+        .collect::<Vec<_>>();
+
+    let population_by_province: std::collections::HashMap<u32, u64> = generated
+        .population
+        .iter()
+        .map(|p| (p.id, p.population))
+        .collect();
+
+    let region_data_js = generated
+        .regions
+        .iter()
+        .map(|r| RegionData {
+            id: r.id,
+            name: r.name.clone(),
+            population: r
+                .province_ids
+                .iter()
+                .map(|id| population_by_province.get(id).copied().unwrap_or(0))
+                .sum(),
+        })
+        .collect::<Vec<_>>();
+
+    let mut region_ids = vec![0u32; generated.pixel_to_id.len()];
+    for y in 0..generated.params.height as usize {
+        for x in 0..generated.params.width as usize {
+            let idx = y * generated.params.width as usize + x;
+            let province_id = generated.pixel_to_id[idx];
+            if let Some(region) = generated
+                .regions
+                .iter()
+                .find(|r| r.province_ids.contains(&province_id))
+            {
+                region_ids[idx] = region.id;
+            }
+        }
+    }
+
+    (
+        province_data,
+        region_data_js,
+        region_ids,
+        generated.population.clone(),
+    )
+}
+
+/// Собирает JS-объект в формате, который отдают `generate_world_with_config`
+/// и `load_world_snapshot`.
+fn build_js_result(
+    width: u32,
+    height: u32,
+    heightmap: &[f32],
+    biomes: &[u32],
+    pixel_to_id: &[u32],
+    region_ids: &[u32],
+    province_data: &[ProvinceData],
+    region_data: &[RegionData],
+    population_data: &[PopulationData],
+) -> Result<JsValue, JsValue> {
     let result = js_sys::Object::new();
 
     // Высотная карта
     js_sys::Reflect::set(
         &result,
         &JsValue::from_str("heightmap"),
-        &Float32Array::from(&heightmap.data[..]).into(),
+        &Float32Array::from(heightmap).into(),
     )
     .map_err(|_| JsValue::from_str("Failed to set heightmap"))?;
 
     // Биомы
-    let biome_data: Vec<u32> = biome_map.data.iter().map(|&b| b as u32).collect();
     js_sys::Reflect::set(
         &result,
         &JsValue::from_str("biomes"),
-        &js_sys::Uint32Array::from(&biome_data[..]).into(),
+        &js_sys::Uint32Array::from(biomes).into(),
     )
     .map_err(|_| JsValue::from_str("Failed to set biomes"))?;
 
@@ -250,80 +470,212 @@ pub fn generate_world_with_config(config_js: JsValue) -> Result<JsValue, JsValue
     js_sys::Reflect::set(
         &result,
         &JsValue::from_str("provinces"),
-        &js_sys::Uint32Array::from(&pixel_to_id[..]).into(),
+        &js_sys::Uint32Array::from(pixel_to_id).into(),
     )
     .map_err(|_| JsValue::from_str("Failed to set provinces"))?;
 
     // Регионы
-    let mut region_data = vec![0u32; pixel_to_id.len()];
-    for y in 0..params.height as usize {
-        for x in 0..params.width as usize {
-            let idx = y * params.width as usize + x;
-            let province_id = pixel_to_id[idx];
-            if let Some(region) = regions
-                .iter()
-                .find(|r| r.province_ids.contains(&province_id))
-            {
-                region_data[idx] = region.id;
-            }
-        }
-    }
     js_sys::Reflect::set(
         &result,
         &JsValue::from_str("regions"),
-        &js_sys::Uint32Array::from(&region_data[..]).into(),
+        &js_sys::Uint32Array::from(region_ids).into(),
     )
     .map_err(|_| JsValue::from_str("Failed to set regions"))?;
 
     // Данные провинций
-    let province_data = provinces
-        .iter()
-        .map(|p| ProvinceData {
-            id: p.id,
-            is_land: p.is_land,
-            coastal: p.coastal,
-            area: p.area,
-            center: [p.center.0, p.center.1],
-        })
-        .collect::<Vec<_>>();
-
     js_sys::Reflect::set(
         &result,
         &JsValue::from_str("provinceData"),
-        &serde_wasm_bindgen::to_value(&province_data).unwrap(),
+        &serde_wasm_bindgen::to_value(province_data).unwrap(),
     )
     .map_err(|_| JsValue::from_str("Failed to set provinceData"))?;
 
     // Данные регионов
-    let region_data_js = regions
-        .iter()
-        .map(|r| RegionData {
-            id: r.id,
-            name: r.name.clone(),
-        })
-        .collect::<Vec<_>>();
-
     js_sys::Reflect::set(
         &result,
         &JsValue::from_str("regionData"),
-        &serde_wasm_bindgen::to_value(&region_data_js).unwrap(),
+        &serde_wasm_bindgen::to_value(region_data).unwrap(),
     )
     .map_err(|_| JsValue::from_str("Failed to set regionData"))?;
 
+    // Данные о населении (provinceId → population)
+    js_sys::Reflect::set(
+        &result,
+        &JsValue::from_str("populationData"),
+        &serde_wasm_bindgen::to_value(population_data).unwrap(),
+    )
+    .map_err(|_| JsValue::from_str("Failed to set populationData"))?;
+
     // Метаданные
     js_sys::Reflect::set(
         &result,
         &JsValue::from_str("width"),
-        &JsValue::from_f64(params.width as f64),
+        &JsValue::from_f64(width as f64),
     )
     .map_err(|_| JsValue::from_str("Failed to set width"))?;
 
     js_sys::Reflect::set(
         &result,
         &JsValue::from_str("height"),
-        &JsValue::from_f64(params.height as f64),
+        &JsValue::from_f64(height as f64),
     )
     .map_err(|_| JsValue::from_str("Failed to set height"))?;
 
     Ok(result.into())
 }
+
+/// Генерирует мир по той же конфигурации, что и [`generate_world_with_config`],
+/// и отдаёт соответствие provinceId → terrain в виде текстового блока в
+/// стиле парадоксовых `terrain.txt`/`default.map`, пригодного для
+/// модостроительных пайплайнов.
+///
+/// # Параметры
+/// * `config_js` — тот же формат конфигурации, что и у
+///   `generate_world_with_config`.
+#[wasm_bindgen]
+pub fn export_paradox_terrain_map(config_js: JsValue) -> Result<String, JsValue> {
+    let config: WorldConfig = serde_wasm_bindgen::from_value(config_js)
+        .map_err(|e| JsValue::from_str(&format!("Invalid config: {}", e)))?;
+
+    let generated = run_pipeline(&config);
+    Ok(build_paradox_terrain_block(
+        &generated.provinces,
+        &generated.terrain,
+    ))
+}
+
+/// Генерирует мир и экспортирует его в виде компактного бинарного снэпшота
+/// (`.world`), который можно сохранить на диск и позже загрузить обратно
+/// через [`load_world_snapshot`] без повторной генерации.
+///
+/// # Параметры
+/// * `config_js` — тот же формат конфигурации, что и у
+///   `generate_world_with_config`.
+#[wasm_bindgen]
+pub fn export_world_snapshot(config_js: JsValue) -> Result<Vec<u8>, JsValue> {
+    let config: WorldConfig = serde_wasm_bindgen::from_value(config_js)
+        .map_err(|e| JsValue::from_str(&format!("Invalid config: {}", e)))?;
+
+    let generated = run_pipeline(&config);
+    let (province_data, region_data, region_ids, population_data) = collect_output_data(&generated);
+
+    let snapshot = WorldSnapshot {
+        config,
+        width: generated.params.width,
+        height: generated.params.height,
+        heightmap: generated.heightmap.data.clone(),
+        biomes: generated.biome_map.data.iter().map(|&b| b as u32).collect(),
+        pixel_to_id: generated.pixel_to_id,
+        region_ids,
+        province_data,
+        region_data,
+        population_data,
+    };
+
+    snapshot::encode(&snapshot).map_err(|e| JsValue::from_str(&e))
+}
+
+/// Загружает ранее экспортированный снэпшот и возвращает тот же JS-объект,
+/// что и `generate_world_with_config` — без повторного прогона конвейера
+/// генерации.
+#[wasm_bindgen]
+pub fn load_world_snapshot(bytes: &[u8]) -> Result<JsValue, JsValue> {
+    let snapshot = snapshot::decode(bytes).map_err(|e| JsValue::from_str(&e))?;
+
+    build_js_result(
+        snapshot.width,
+        snapshot.height,
+        &snapshot.heightmap,
+        &snapshot.biomes,
+        &snapshot.pixel_to_id,
+        &snapshot.region_ids,
+        &snapshot.province_data,
+        &snapshot.region_data,
+        &snapshot.population_data,
+    )
+}
+
+/// Генерирует мир по той же конфигурации, что и [`generate_world_with_config`],
+/// и векторизует береговую линию маршем квадратов (marching squares) по
+/// растру типов воды, упрощая каждое кольцо Дугласом—Пекером.
+///
+/// # Параметры
+/// * `config_js` — тот же формат конфигурации, что и у
+///   `generate_world_with_config`.
+/// * `simplify_tolerance` — допуск упрощения Дугласа—Пекера в пикселях
+///   карты высот; `0.0` отключает упрощение.
+///
+/// # Возвращает
+/// Объект с полями:
+/// - `ringCoords` — Float32Array, координаты всех колец подряд (x, y, x, y, …)
+/// - `ringLengths` — Uint32Array, число точек в каждом кольце по порядку
+///
+/// Кольца с положительной площадью (по формуле шнурования) — внешние
+/// границы массивов суши, с отрицательной — внутренние озёра.
+#[wasm_bindgen]
+pub fn generate_coastlines(
+    config_js: JsValue,
+    simplify_tolerance: f32,
+) -> Result<JsValue, JsValue> {
+    let config: WorldConfig = serde_wasm_bindgen::from_value(config_js)
+        .map_err(|e| JsValue::from_str(&format!("Invalid config: {}", e)))?;
+
+    let generated = run_pipeline(&config);
+    let rings = vectorize_coastlines(
+        &generated.water_type,
+        &generated.heightmap.data,
+        generated.sea_level,
+        generated.params.width,
+        generated.params.height,
+        simplify_tolerance,
+    );
+
+    let ring_lengths: Vec<u32> = rings.iter().map(|r| r.len() as u32).collect();
+    let ring_coords: Vec<f32> = rings.iter().flatten().flat_map(|&(x, y)| [x, y]).collect();
+
+    let result = js_sys::Object::new();
+    js_sys::Reflect::set(
+        &result,
+        &JsValue::from_str("ringCoords"),
+        &Float32Array::from(ring_coords.as_slice()).into(),
+    )
+    .map_err(|_| JsValue::from_str("Failed to set ringCoords"))?;
+    js_sys::Reflect::set(
+        &result,
+        &JsValue::from_str("ringLengths"),
+        &js_sys::Uint32Array::from(ring_lengths.as_slice()).into(),
+    )
+    .map_err(|_| JsValue::from_str("Failed to set ringLengths"))?;
+
+    Ok(result.into())
+}
+
+/// Генерирует мир так же, как [`generate_coastlines`], но отдаёт кольца
+/// береговой линии в виде строки GeoJSON `MultiPolygon` — внешние границы
+/// массивов суши с вложенными в них озёрами-«дырками».
+///
+/// # Параметры
+/// * `config_js` — тот же формат конфигурации, что и у
+///   `generate_world_with_config`.
+/// * `simplify_tolerance` — допуск упрощения Дугласа—Пекера в пикселях
+///   карты высот; `0.0` отключает упрощение.
+#[wasm_bindgen]
+pub fn generate_coastlines_geojson(
+    config_js: JsValue,
+    simplify_tolerance: f32,
+) -> Result<String, JsValue> {
+    let config: WorldConfig = serde_wasm_bindgen::from_value(config_js)
+        .map_err(|e| JsValue::from_str(&format!("Invalid config: {}", e)))?;
+
+    let generated = run_pipeline(&config);
+    let rings = vectorize_coastlines(
+        &generated.water_type,
+        &generated.heightmap.data,
+        generated.sea_level,
+        generated.params.width,
+        generated.params.height,
+        simplify_tolerance,
+    );
+
+    Ok(to_geojson_multipolygon(&rings))
+}
@@ -0,0 +1,109 @@
+// src/population.rs
+//! Распределение населения по провинциям на основе пригодности клеток
+//! для проживания (биом, температура, влажность).
+
+use std::collections::HashMap;
+
+use mapgen::{biome::Biome, biome::BiomeMap, province::Province};
+
+/// Население одной провинции для передачи в JavaScript.
+#[derive(serde::Serialize, serde::Deserialize, Clone)]
+pub(crate) struct PopulationData {
+    pub(crate) id: u32,
+    pub(crate) population: u64,
+}
+
+/// Минимальное население, которое всё ещё способна прокормить пригодная
+/// для жизни сухопутная провинция.
+const MIN_CARRYING_CAPACITY: u64 = 500;
+
+/// Плотность населения (людей на клетку площади), выше которой провинция
+/// считается перенаселённой и население ограничивается площадью.
+const AREA_CAP_DENSITY: f32 = 200.0;
+
+/// Множитель населения для прибрежных провинций — исторически они
+/// поддерживают более плотное заселение за счёт торговли и рыболовства.
+const COASTAL_BONUS: f32 = 1.25;
+
+/// Оценивает пригодность клетки для проживания: пик в умеренных
+/// биомах (лес, степь) с умеренной влажностью, около нуля во льдах,
+/// пустынях и высокогорьях.
+fn habitability(biome: Biome, temperature: f32, moisture: f32) -> f32 {
+    let biome_factor = match biome {
+        Biome::Ice => 0.0,
+        Biome::Desert => 0.05,
+        Biome::Forest => 1.0,
+        Biome::Steppe => 0.8,
+        Biome::Jungle => 0.5,
+        Biome::Wetlands => 0.3,
+        _ => 0.6,
+    };
+
+    // И температура, и влажность пригодны сильнее всего в умеренном
+    // диапазоне (около 0.5 в нормализованной шкале [0, 1]).
+    let temp_factor = 1.0 - ((temperature - 0.5).abs() * 2.0).clamp(0.0, 1.0);
+    let moisture_factor = 1.0 - ((moisture - 0.5).abs() * 2.0).clamp(0.0, 1.0);
+
+    biome_factor * temp_factor * moisture_factor
+}
+
+/// Распределяет `total_population` по сухопутным провинциям
+/// пропорционально суммарной пригодности их клеток, с минимальным
+/// порогом заселённости и ограничением по площади.
+pub(crate) fn distribute_population(
+    provinces: &[Province],
+    pixel_to_id: &[u32],
+    biome_map: &BiomeMap,
+    temperature: &[f32],
+    humidity: &[f32],
+    total_population: u64,
+) -> Vec<PopulationData> {
+    let mut habitability_sum: HashMap<u32, f32> = HashMap::new();
+    for (idx, &province_id) in pixel_to_id.iter().enumerate() {
+        let score = habitability(biome_map.data[idx], temperature[idx], humidity[idx]);
+        *habitability_sum.entry(province_id).or_insert(0.0) += score;
+    }
+
+    let mut weights = Vec::with_capacity(provinces.len());
+    let mut total_weight = 0.0f32;
+    for province in provinces {
+        let mut weight = if province.is_land {
+            habitability_sum.get(&province.id).copied().unwrap_or(0.0)
+        } else {
+            0.0
+        };
+        if province.coastal {
+            weight *= COASTAL_BONUS;
+        }
+        weights.push(weight);
+        total_weight += weight;
+    }
+
+    provinces
+        .iter()
+        .zip(weights.iter())
+        .map(|(province, &weight)| {
+            let population = if !province.is_land
+                || total_population == 0
+                || weight <= f32::EPSILON
+                || total_weight <= 0.0
+            {
+                0
+            } else {
+                let area_cap = (province.area as f32 * AREA_CAP_DENSITY).round() as u64;
+                let proportional_share =
+                    (total_population as f32 * (weight / total_weight)).round() as u64;
+                // Минимальный порог гарантирует, что слабо пригодная
+                // провинция не обнуляется при округлении — ценой того, что
+                // суммарное распределённое население может превысить
+                // `total_population`, если пригодных провинций много.
+                proportional_share.max(MIN_CARRYING_CAPACITY).min(area_cap)
+            };
+
+            PopulationData {
+                id: province.id,
+                population,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,37 @@
+// src/snapshot.rs
+//! Бинарный снэпшот результата генерации — чтобы сохранить сгенерированный
+//! мир на диск и позже загрузить его обратно без повторного прогона
+//! конвейера.
+
+use serde::{Deserialize, Serialize};
+
+use crate::population::PopulationData;
+use crate::{ProvinceData, RegionData, WorldConfig};
+
+/// Полный снэпшот сгенерированного мира: всё, что отдаёт
+/// `generate_world_with_config`, плюс конфигурация, по которой мир был
+/// построен. Этого достаточно, чтобы восстановить тот же JS-объект без
+/// повторной генерации.
+#[derive(Serialize, Deserialize)]
+pub(crate) struct WorldSnapshot {
+    pub(crate) config: WorldConfig,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) heightmap: Vec<f32>,
+    pub(crate) biomes: Vec<u32>,
+    pub(crate) pixel_to_id: Vec<u32>,
+    pub(crate) region_ids: Vec<u32>,
+    pub(crate) province_data: Vec<ProvinceData>,
+    pub(crate) region_data: Vec<RegionData>,
+    pub(crate) population_data: Vec<PopulationData>,
+}
+
+/// Кодирует снэпшот в компактный бинарный формат (bincode).
+pub(crate) fn encode(snapshot: &WorldSnapshot) -> Result<Vec<u8>, String> {
+    bincode::serialize(snapshot).map_err(|e| format!("Failed to encode world snapshot: {e}"))
+}
+
+/// Декодирует снэпшот, ранее полученный через [`encode`].
+pub(crate) fn decode(bytes: &[u8]) -> Result<WorldSnapshot, String> {
+    bincode::deserialize(bytes).map_err(|e| format!("Failed to decode world snapshot: {e}"))
+}
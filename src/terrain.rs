@@ -0,0 +1,125 @@
+// src/terrain.rs
+//! Классификация рельефа провинций и экспорт в формате, близком к
+//! Paradox-овым `default.map`/`terrain.txt`.
+
+use mapgen::{
+    biome::{Biome, BiomeMap},
+    province::Province,
+    Heightmap,
+};
+
+/// Тип рельефа, присваиваемый провинции.
+///
+/// Для водных провинций используются `Sea`/`CoastalSea`, для сухопутных —
+/// один из типов ниже, выбранный по высоте, влажности, широте и биому
+/// клетки-центра провинции.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) enum ProvinceTerrain {
+    Plains,
+    Farmlands,
+    Hills,
+    Mountains,
+    DesertMountains,
+    Desert,
+    Jungle,
+    Forest,
+    Steppe,
+    Wetlands,
+    Sea,
+    CoastalSea,
+}
+
+impl ProvinceTerrain {
+    /// Идентификатор террейна в стиле, который ожидают парадоксовые
+    /// тулчейны модостроения (`terrain.txt`/`default.map`).
+    pub(crate) fn paradox_id(self) -> &'static str {
+        match self {
+            ProvinceTerrain::Plains => "plains",
+            ProvinceTerrain::Farmlands => "farmlands",
+            ProvinceTerrain::Hills => "hills",
+            ProvinceTerrain::Mountains => "mountains",
+            ProvinceTerrain::DesertMountains => "desert_mountains",
+            ProvinceTerrain::Desert => "desert",
+            ProvinceTerrain::Jungle => "jungle",
+            ProvinceTerrain::Forest => "forest",
+            ProvinceTerrain::Steppe => "steppe",
+            ProvinceTerrain::Wetlands => "wetlands",
+            ProvinceTerrain::Sea => "sea",
+            ProvinceTerrain::CoastalSea => "coastal_sea",
+        }
+    }
+}
+
+/// Классифицирует рельеф каждой провинции по высоте/влажности/широте
+/// клетки-центра и биому в ней.
+pub(crate) fn classify_province_terrain(
+    provinces: &[Province],
+    heightmap: &Heightmap,
+    biome_map: &BiomeMap,
+    humidity: &[f32],
+    sea_level: f32,
+    width: u32,
+    height: u32,
+) -> Vec<ProvinceTerrain> {
+    provinces
+        .iter()
+        .map(|province| {
+            if !province.is_land {
+                return if province.coastal {
+                    ProvinceTerrain::CoastalSea
+                } else {
+                    ProvinceTerrain::Sea
+                };
+            }
+
+            let x = (province.center.0.round() as i64).clamp(0, width as i64 - 1) as usize;
+            let y = (province.center.1.round() as i64).clamp(0, height as i64 - 1) as usize;
+            let idx = y * width as usize + x;
+
+            let elevation_above_sea = (heightmap.data[idx] - sea_level).max(0.0);
+            let moisture = humidity.get(idx).copied().unwrap_or(0.0);
+            // 0.0 на экваторе, 1.0 на полюсах.
+            let latitude = (y as f32 / height as f32 - 0.5).abs() * 2.0;
+            let biome = biome_map.data[idx];
+
+            match biome {
+                Biome::Desert if elevation_above_sea > 0.25 => ProvinceTerrain::DesertMountains,
+                Biome::Desert => ProvinceTerrain::Desert,
+                _ if elevation_above_sea > 0.3 => ProvinceTerrain::Mountains,
+                _ if elevation_above_sea > 0.15 => ProvinceTerrain::Hills,
+                Biome::Jungle if latitude < 0.3 && moisture > 0.6 => ProvinceTerrain::Jungle,
+                Biome::Forest => ProvinceTerrain::Forest,
+                Biome::Wetlands => ProvinceTerrain::Wetlands,
+                Biome::Steppe => ProvinceTerrain::Steppe,
+                _ if moisture > 0.7 => ProvinceTerrain::Wetlands,
+                _ if moisture < 0.25 => ProvinceTerrain::Steppe,
+                _ if elevation_above_sea > 0.05 && moisture > 0.35 => ProvinceTerrain::Farmlands,
+                _ => ProvinceTerrain::Plains,
+            }
+        })
+        .collect()
+}
+
+/// Сериализует карту provinceId → terrain в текстовый блок, пригодный для
+/// импорта в парадоксовые модостроительные тулчейны.
+///
+/// Формат намеренно упрощён до плоского списка `province_id = terrain_id`
+/// внутри блока `terrain = { ... }`, как в `terrain.txt` из `default.map` —
+/// это подмножество, не полноценный файл провинций. Морские провинции
+/// (`Sea`/`CoastalSea`) в блок не включаются: в парадоксовых тулчейнах
+/// `terrain.txt` назначает тип рельефа только сухопутным провинциям, вода
+/// определяется отдельно через `default.map`.
+pub(crate) fn build_paradox_terrain_block(
+    provinces: &[Province],
+    terrain: &[ProvinceTerrain],
+) -> String {
+    let mut out = String::from("# Auto-generated by mapgen-web-demo\nterrain = {\n");
+    for (province, terrain) in provinces.iter().zip(terrain.iter()) {
+        if !province.is_land {
+            continue;
+        }
+        out.push_str(&format!("\t{} = {}\n", province.id, terrain.paradox_id()));
+    }
+    out.push_str("}\n");
+    out
+}